@@ -49,5 +49,5 @@ fn main() {
                 ),
         );
 
-    generate(Spec, &mut cmd, "example", &mut io::stdout());
+    generate(Spec::default(), &mut cmd, "example", &mut io::stdout());
 }
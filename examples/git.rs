@@ -1,5 +1,5 @@
 use carapace_spec_clap::Spec;
-use clap::{arg, Command};
+use clap::{arg, Command, ValueHint};
 use clap_complete::generate;
 use std::io;
 use std::path::PathBuf;
@@ -21,7 +21,7 @@ fn git_cmd() -> Command {
                 .about("Compare two commits")
                 .arg(arg!(base: [COMMIT]))
                 .arg(arg!(head: [COMMIT]))
-                .arg(arg!(path: [PATH]).last(true))
+                .arg(arg!(path: [PATH]).last(true).value_hint(ValueHint::AnyPath))
                 .arg(
                     arg!(--color <WHEN>)
                         .value_parser(["always", "auto", "never"])
@@ -59,5 +59,5 @@ fn push_args() -> Vec<clap::Arg> {
 
 fn main() {
     let mut cmd = git_cmd();
-    generate(Spec, &mut cmd, "myapp", &mut io::stdout());
+    generate(Spec::default(), &mut cmd, "myapp", &mut io::stdout());
 }
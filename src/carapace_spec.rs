@@ -1,51 +1,220 @@
+use crate::{flag, Alias, Flag, FlagKey, SpecError, ValueKind};
 use clap::{
-    Arg, ArgAction,
+    builder::{PossibleValue, ValueParser},
+    parser::ValueSource,
+    Arg, ArgAction, ArgGroup, Id,
     ValueHint::{self, *},
 };
 use clap_complete::*;
 use indexmap::IndexMap as Map;
-use serde::Serialize;
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+const SCHEMA: &str = "https://carapace.sh/schemas/command.json";
 
 fn is_default<T: Default + PartialEq>(value: &T) -> bool {
     *value == T::default()
 }
 
-#[derive(Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Command {
     pub name: String,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub aliases: Vec<String>,
     pub description: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub group: String,
     #[serde(skip_serializing_if = "is_default")]
     pub hidden: bool,
-    #[serde(skip_serializing_if = "Map::is_empty")]
-    pub flags: Map<String, FlagValue>,
-    #[serde(skip_serializing_if = "Map::is_empty")]
-    pub persistentflags: Map<String, FlagValue>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub parsing: Parsing,
+    #[serde(skip_serializing_if = "Vec::is_empty", with = "flag::map")]
+    pub flags: Vec<Flag>,
+    #[serde(skip_serializing_if = "Vec::is_empty", with = "flag::map")]
+    pub persistentflags: Vec<Flag>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclusiveflags: Vec<Vec<String>>,
+    /// Command line the command expands to, e.g. `[git, log, --oneline]`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub run: String,
     #[serde(skip_serializing_if = "Completion::is_empty")]
     pub completion: Completion,
     #[serde(skip_serializing_if = "Documentation::is_empty")]
     pub documentation: Documentation,
+    /// Example command lines with their description.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub examples: Map<String, String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub commands: Vec<Command>,
 }
 
-#[derive(Default, Serialize)]
+impl Command {
+    /// Deep-merges `overlay` into the command.
+    ///
+    /// - `description`, `group`, `parsing` and `documentation.command` are replaced unless
+    ///   empty/default
+    /// - `hidden` is set if the overlay is hidden
+    /// - `aliases` and `exclusiveflags` are extended with missing entries
+    /// - flags replace the flag sharing a shorthand, longhand or alias (modifiers are ignored)
+    ///   but keep its names, others are appended
+    /// - `completion.flag` and `documentation.flag` entries replace those for the same flag,
+    ///   `examples` those for the same command line
+    /// - `positional` and `dash` slots of completion and documentation are replaced unless
+    ///   empty, `positionalany` and `dashany` are replaced unless empty
+    /// - subcommands are merged by name, unknown ones are appended
+    ///
+    /// The name of the overlay itself is ignored.
+    pub fn merge(&mut self, overlay: Command) {
+        if !overlay.description.is_empty() {
+            self.description = overlay.description;
+        }
+        if !overlay.group.is_empty() {
+            self.group = overlay.group;
+        }
+        self.hidden |= overlay.hidden;
+        if !overlay.run.is_empty() {
+            self.run = overlay.run;
+        }
+        if overlay.parsing != Parsing::default() {
+            self.parsing = overlay.parsing;
+        }
+
+        merge_missing(&mut self.aliases, overlay.aliases);
+        merge_flags(&mut self.flags, overlay.flags);
+        merge_flags(&mut self.persistentflags, overlay.persistentflags);
+        merge_missing(&mut self.exclusiveflags, overlay.exclusiveflags);
+
+        self.completion.flag.extend(overlay.completion.flag);
+        merge_slots(
+            &mut self.completion.positional,
+            overlay.completion.positional,
+        );
+        if !overlay.completion.positionalany.is_empty() {
+            self.completion.positionalany = overlay.completion.positionalany;
+        }
+        merge_slots(&mut self.completion.dash, overlay.completion.dash);
+        if !overlay.completion.dashany.is_empty() {
+            self.completion.dashany = overlay.completion.dashany;
+        }
+
+        if !overlay.documentation.command.is_empty() {
+            self.documentation.command = overlay.documentation.command;
+        }
+        self.documentation.flag.extend(overlay.documentation.flag);
+        merge_slots(
+            &mut self.documentation.positional,
+            overlay.documentation.positional,
+        );
+        if !overlay.documentation.positionalany.is_empty() {
+            self.documentation.positionalany = overlay.documentation.positionalany;
+        }
+        merge_slots(&mut self.documentation.dash, overlay.documentation.dash);
+        if !overlay.documentation.dashany.is_empty() {
+            self.documentation.dashany = overlay.documentation.dashany;
+        }
+        self.examples.extend(overlay.examples);
+
+        for subcommand in overlay.commands {
+            match self.commands.iter_mut().find(|c| c.name == subcommand.name) {
+                Some(existing) => existing.merge(subcommand),
+                None => self.commands.push(subcommand),
+            }
+        }
+    }
+}
+
+fn merge_missing<T: PartialEq>(target: &mut Vec<T>, overlay: Vec<T>) {
+    for item in overlay {
+        if !target.contains(&item) {
+            target.push(item);
+        }
+    }
+}
+
+fn merge_flags(target: &mut Vec<Flag>, overlay: Vec<Flag>) {
+    for flag in overlay {
+        match target.iter_mut().find(|f| shares_name(f, &flag)) {
+            Some(existing) => {
+                *existing = Flag {
+                    short: existing.short,
+                    long: existing.long.take(),
+                    aliases: std::mem::take(&mut existing.aliases),
+                    hidden_aliases: std::mem::take(&mut existing.hidden_aliases),
+                    ..flag
+                };
+            }
+            None => target.push(flag),
+        }
+    }
+}
+
+/// Whether the flags have a shorthand, longhand or alias in common.
+fn shares_name(flag: &Flag, other: &Flag) -> bool {
+    let names = names_of(flag);
+    names_of(other).iter().any(|n| names.contains(n))
+}
+
+fn names_of(flag: &Flag) -> Vec<Alias> {
+    flag.short
+        .map(Alias::Short)
+        .into_iter()
+        .chain(flag.long.clone().map(Alias::Long))
+        .chain(flag.aliases.iter().cloned())
+        .chain(flag.hidden_aliases.iter().cloned())
+        .collect()
+}
+
+fn merge_slots<T: Clone + Default + PartialEq>(target: &mut Vec<T>, overlay: Vec<T>) {
+    if target.len() < overlay.len() {
+        target.resize(overlay.len(), T::default());
+    }
+    for (slot, value) in target.iter_mut().zip(overlay) {
+        if value != T::default() {
+            *slot = value;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Parsing {
+    #[default]
+    Interspersed,
+    NonInterspersed,
+    Disabled,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Documentation {
     #[serde(skip_serializing_if = "String::is_empty")]
     pub command: String,
     #[serde(skip_serializing_if = "Map::is_empty")]
     pub flag: Map<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub positional: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub positionalany: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dash: Vec<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub dashany: String,
 }
 
 impl Documentation {
     fn is_empty(&self) -> bool {
-        self.command.is_empty() && self.flag.is_empty()
+        self.command.is_empty()
+            && self.flag.is_empty()
+            && self.positional.is_empty()
+            && self.positionalany.is_empty()
+            && self.dash.is_empty()
+            && self.dashany.is_empty()
     }
 }
 
-#[derive(Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Completion {
     #[serde(skip_serializing_if = "Map::is_empty")]
     pub flag: Map<String, Vec<String>>,
@@ -69,14 +238,15 @@ impl Completion {
     }
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum FlagValue {
     Plain(String),
     Extended(ExtendedFlag),
 }
 
-#[derive(Clone, Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ExtendedFlag {
     #[serde(skip_serializing_if = "String::is_empty")]
     pub description: String,
@@ -84,13 +254,320 @@ pub struct ExtendedFlag {
     pub nargs: i64,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub default: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub tag: String,
 }
 
 fn is_zero(value: &i64) -> bool {
     *value == 0
 }
 
-pub struct Spec;
+/// Generates a [carapace-spec](https://carapace.sh/) from a [`clap::Command`].
+///
+/// `Spec::default()` yields the builtin mapping, which can be adjusted with the builder methods.
+///
+/// Positionals with a fixed number of values are completed in `positional`, one slot per value.
+/// Carapace can't express slots that follow a positional taking a variable number of values,
+/// like a variadic `<SRC>... <DST>` or `num_args(1..=3)`: their position depends on the number
+/// of words given. So the first such positional and all positionals after it share
+/// `positionalany`, i.e. `<DST>` is also completed with the values of `<SRC>` and vice versa.
+#[derive(Clone, Default)]
+pub struct Spec {
+    hints: Map<ValueHint, Vec<String>>,
+    actions: Map<Id, Vec<String>>,
+    strip_hidden: bool,
+    external_subcommands: Option<Vec<String>>,
+    overlays: Vec<Command>,
+    default_group: Option<String>,
+    order: Order,
+    examples: Map<String, Map<String, String>>,
+    extract_examples: bool,
+    shortcuts: Map<String, Vec<String>>,
+}
+
+/// Order of flags, completions, documentation and subcommands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Flags sorted by name, subcommands in declaration order.
+    #[default]
+    Alphabetical,
+    /// Everything in declaration order.
+    Declaration,
+    /// Everything ordered like `--help`, i.e. by `display_order` and name.
+    DisplayOrder,
+}
+
+impl Spec {
+    /// Creates a generator with the builtin mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Completes values with the given `hint` using `macros` instead of the builtin mapping:
+    ///
+    /// | hint                                                   | completion                                        |
+    /// |--------------------------------------------------------|---------------------------------------------------|
+    /// | `AnyPath`, `FilePath`, `ExecutablePath`                | `$files`                                          |
+    /// | `DirPath`                                              | `$directories`                                    |
+    /// | `CommandName`, `CommandString`                         | `$executables`, `$files`                          |
+    /// | `CommandWithArguments`                                 | `$carapace.bridge.CarapaceBin`                    |
+    /// | `Username`                                             | `$carapace.os.Users`                              |
+    /// | `Hostname`                                             | `$carapace.net.Hosts`                             |
+    /// | `Url`                                                  | common schemes, `$nospace(/)`                     |
+    /// | `EmailAddress`                                         | `$carapace.os.Users`, `$suffix(@)`, `$nospace(@)` |
+    /// | `Other`, `Unknown`                                     | none                                              |
+    ///
+    /// `CommandWithArguments` is always a trailing positional, so the nested command line ends
+    /// up in `positionalany`/`dashany`: `$carapace.bridge.CarapaceBin` completes the command name
+    /// for the first word and delegates the remaining words to the completer of that command.
+    /// Preceding positionals are skipped with `$shift(n)`.
+    ///
+    /// `Other` marks free-form text and `Unknown` the absence of a hint, so neither
+    /// adds a completion and only possible values (if any) are offered.
+    ///
+    /// An empty list disables completion for the hint.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// # use clap::ValueHint;
+    /// let spec = Spec::new().hint(ValueHint::FilePath, ["$files([.toml, .yaml])"]);
+    /// ```
+    pub fn hint<I, S>(mut self, hint: ValueHint, macros: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.hints
+            .insert(hint, macros.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Completes values of every argument with the given `id` using `macros`.
+    ///
+    /// This takes precedence over the value hint of the argument, possible values are still added.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// let spec = Spec::new().action("branch", ["$carapace.tools.git.LocalBranches"]);
+    /// ```
+    pub fn action<I, S>(mut self, id: impl Into<Id>, macros: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.actions
+            .insert(id.into(), macros.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Omits hidden subcommands instead of emitting them with `hidden: true`.
+    pub fn strip_hidden(mut self, yes: bool) -> Self {
+        self.strip_hidden = yes;
+        self
+    }
+
+    /// Completes names of external subcommands using `macros` instead of `$executables`.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// let spec = Spec::new().external_subcommands(["$carapace.tools.git.Plugins"]);
+    /// ```
+    pub fn external_subcommands<I, S>(mut self, macros: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.external_subcommands = Some(macros.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Groups subcommands of commands without a [`clap::Command::subcommand_help_heading`]
+    /// under `group`.
+    pub fn default_group(mut self, group: impl Into<String>) -> Self {
+        self.default_group = Some(group.into());
+        self
+    }
+
+    /// Orders flags, completions, documentation and subcommands.
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Adds examples to the command at `path`, given as space separated subcommand names
+    /// (`""` for the root command).
+    ///
+    /// Generating the spec fails with [`SpecError::UnknownCommand`] if there is no such
+    /// command.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// let spec = Spec::new().examples(
+    ///     "stash push",
+    ///     [("git stash push -m wip", "stash changes with a message")],
+    /// );
+    /// ```
+    pub fn examples<I, K, V>(mut self, path: &str, examples: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let path = path.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.examples.entry(path).or_default().extend(
+            examples
+                .into_iter()
+                .map(|(example, description)| (example.into(), description.into())),
+        );
+        self
+    }
+
+    /// Marks the command at `path` as a shortcut that expands to `target`, a full command
+    /// line including the program name.
+    ///
+    /// The command is emitted with `run` set and without flags or completions, so that
+    /// completion is delegated to the expanded command. Generating the spec fails with
+    /// [`SpecError::UnknownCommand`] unless `path` names a subcommand, so the root command
+    /// (`""`) can't be a shortcut.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// let spec = Spec::new().shortcut("lg", ["git", "log", "--oneline"]);
+    /// ```
+    pub fn shortcut<I, S>(mut self, path: &str, target: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let path = path.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.shortcuts
+            .insert(path, target.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Extracts examples from an `Examples:` section in `after_help`/`after_long_help`.
+    ///
+    /// Each indented line of the section is an example, optionally prefixed with `$ `.
+    /// Lines starting with `#` describe the example that follows.
+    pub fn extract_examples(mut self, yes: bool) -> Self {
+        self.extract_examples = yes;
+        self
+    }
+
+    /// Merges `overlay` into the generated spec, see [`Command::merge`] for the rules.
+    ///
+    /// Overlays are applied in the order they were added.
+    pub fn overlay(mut self, overlay: Command) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    /// Merges the YAML spec `overlay` into the generated spec.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// let spec = Spec::new()
+    ///     .overlay_yaml("
+    /// name: git
+    /// commands:
+    ///   - name: checkout
+    ///     completion:
+    ///       flag:
+    ///         branch: [$carapace.tools.git.LocalBranches]
+    /// ")
+    ///     .unwrap();
+    /// ```
+    pub fn overlay_yaml(self, overlay: &str) -> Result<Self, SpecError> {
+        Ok(self.overlay(Self::parse_yaml(overlay)?))
+    }
+
+    fn command_for(&self, cmd: &clap::Command) -> Result<Command, SpecError> {
+        // globals are only propagated and possible values only resolved on a built command
+        let mut cmd = cmd.clone();
+        cmd.build();
+
+        let mut command = command_for(self, &cmd, None, &[]);
+        filter_inherited_flags(&mut command, &mut Map::new(), &mut Map::new());
+        for (path, examples) in &self.examples {
+            check_path(&cmd, path)?;
+            if let Some(subcommand) = find_command(&mut command, path) {
+                subcommand.examples.extend(examples.clone());
+            }
+        }
+        for (path, target) in &self.shortcuts {
+            // the root itself can't be replaced by a shortcut
+            if path.is_empty() {
+                return Err(SpecError::UnknownCommand(path.clone()));
+            }
+            check_path(&cmd, path)?;
+            if let Some(subcommand) = find_command(&mut command, path) {
+                *subcommand = Command {
+                    name: subcommand.name.clone(),
+                    aliases: subcommand.aliases.clone(),
+                    description: subcommand.description.clone(),
+                    group: subcommand.group.clone(),
+                    hidden: subcommand.hidden,
+                    parsing: Parsing::Disabled,
+                    // a JSON array is a YAML flow sequence with every word quoted
+                    run: serde_json::to_string(target)?,
+                    ..Default::default()
+                };
+            }
+        }
+        for overlay in &self.overlays {
+            command.merge(overlay.clone());
+        }
+        Ok(command)
+    }
+
+    fn action_for(&self, arg: &Arg) -> Vec<String> {
+        if let Some(actions) = self.actions.get(arg.get_id()) {
+            return actions.clone();
+        }
+
+        let hint = arg.get_value_hint();
+        self.hints
+            .get(&hint)
+            .cloned()
+            .unwrap_or_else(|| action_for(hint))
+    }
+}
+
+impl Spec {
+    /// Writes the YAML spec for `cmd` to `buf`.
+    ///
+    /// Like [`Generator::generate`], but returns errors instead of panicking.
+    pub fn try_generate(&self, cmd: &clap::Command, buf: &mut dyn Write) -> Result<(), SpecError> {
+        Self::write(&self.command_for(cmd)?, buf)
+    }
+
+    /// Writes `command` as YAML spec to `buf`.
+    pub fn write(command: &Command, buf: &mut dyn Write) -> Result<(), SpecError> {
+        let serialized = yaml_serde::to_string(command)?;
+
+        buf.write_all(format!("# yaml-language-server: $schema={SCHEMA}\n").as_bytes())?;
+        buf.write_all(serialized.as_bytes())?;
+        Ok(())
+    }
+
+    /// Parses a YAML spec.
+    ///
+    /// ```
+    /// # use carapace_spec_clap::Spec;
+    /// let command = Spec::parse_yaml("name: example\nflags:\n  -v, --verbose*: verbosity").unwrap();
+    /// let flag = command.flags.first().unwrap();
+    /// assert_eq!(flag.long.as_deref(), Some("verbose"));
+    /// assert!(flag.repeatable);
+    /// ```
+    pub fn parse_yaml(s: &str) -> Result<Command, SpecError> {
+        Ok(yaml_serde::from_str(s)?)
+    }
+
+    /// Parses a YAML spec from `reader`.
+    pub fn from_reader(reader: impl Read) -> Result<Command, SpecError> {
+        Ok(yaml_serde::from_reader(reader)?)
+    }
+}
 
 impl Generator for Spec {
     fn file_name(&self, name: &str) -> String {
@@ -98,25 +575,78 @@ impl Generator for Spec {
     }
 
     fn generate(&self, cmd: &clap::Command, buf: &mut dyn Write) {
-        let mut command = command_for(cmd);
-        filter_inherited_flags(&mut command, &mut Map::new(), &mut Map::new());
+        self.try_generate(cmd, buf)
+            .unwrap_or_else(|e| panic!("spec generator: {e}"));
+    }
+}
 
-        let serialized =
-            yaml_serde::to_string(&command).expect("spec generator: YAML serialization failed");
+/// Generates a carapace-spec in JSON format.
+///
+/// ```
+/// # use carapace_spec_clap::{Spec, SpecJson};
+/// # use clap::ValueHint;
+/// let spec = SpecJson(Spec::new().hint(ValueHint::FilePath, ["$files([.json])"]));
+/// ```
+#[derive(Clone, Default)]
+pub struct SpecJson(pub Spec);
+
+#[derive(Serialize)]
+struct JsonDocument<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    #[serde(flatten)]
+    command: &'a Command,
+}
 
-        buf.write_all(
-            b"# yaml-language-server: $schema=https://carapace.sh/schemas/command.json\n",
-        )
-        .expect("spec generator: failed writing schema header");
+impl SpecJson {
+    /// Writes the JSON spec for `cmd` to `buf`.
+    ///
+    /// Like [`Generator::generate`], but returns errors instead of panicking.
+    pub fn try_generate(&self, cmd: &clap::Command, buf: &mut dyn Write) -> Result<(), SpecError> {
+        Self::write(&self.0.command_for(cmd)?, buf)
+    }
+
+    /// Writes `command` as JSON spec to `buf`.
+    pub fn write(command: &Command, buf: &mut dyn Write) -> Result<(), SpecError> {
+        let document = JsonDocument {
+            schema: SCHEMA,
+            command,
+        };
 
-        buf.write_all(serialized.as_bytes())
-            .expect("spec generator: failed writing YAML output");
+        serde_json::to_writer_pretty(&mut *buf, &document)?;
+        buf.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Parses a JSON spec.
+    pub fn parse_json(s: &str) -> Result<Command, SpecError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Parses a JSON spec from `reader`.
+    pub fn from_reader(reader: impl Read) -> Result<Command, SpecError> {
+        Ok(serde_json::from_reader(reader)?)
     }
 }
 
-fn filter_inherited_flags(cmd: &mut Command, inherited: &mut Map<String, FlagValue>, inherited_doc: &mut Map<String, ()>) {
+impl Generator for SpecJson {
+    fn file_name(&self, name: &str) -> String {
+        format!("{name}.json")
+    }
+
+    fn generate(&self, cmd: &clap::Command, buf: &mut dyn Write) {
+        self.try_generate(cmd, buf)
+            .unwrap_or_else(|e| panic!("spec generator: {e}"));
+    }
+}
+
+fn filter_inherited_flags(
+    cmd: &mut Command,
+    inherited: &mut Map<FlagKey, ()>,
+    inherited_doc: &mut Map<String, ()>,
+) {
     cmd.persistentflags
-        .retain(|k, _| !inherited.contains_key(k));
+        .retain(|f| !inherited.contains_key(&f.key()));
 
     cmd.documentation
         .flag
@@ -125,9 +655,9 @@ fn filter_inherited_flags(cmd: &mut Command, inherited: &mut Map<String, FlagVal
     let added: Vec<_> = cmd
         .persistentflags
         .iter()
-        .map(|(k, v)| {
-            inherited.insert(k.clone(), v.clone());
-            k.clone()
+        .map(|f| {
+            inherited.insert(f.key(), ());
+            f.key()
         })
         .collect();
 
@@ -154,116 +684,404 @@ fn filter_inherited_flags(cmd: &mut Command, inherited: &mut Map<String, FlagVal
     }
 }
 
-fn command_for(cmd: &clap::Command) -> Command {
+fn command_for(
+    spec: &Spec,
+    cmd: &clap::Command,
+    parent: Option<&clap::Command>,
+    inherited: &[Conflict],
+) -> Command {
+    let conflicts = global_conflicts_for(cmd, parent, inherited);
+    let positionals = positional_slots_for(spec, cmd);
+    let dash = dash_slots_for(spec, cmd);
+
     Command {
         name: cmd.get_name().to_owned(),
         aliases: cmd.get_all_aliases().map(str::to_owned).collect(),
         description: cmd.get_about().unwrap_or_default().to_string(),
+        group: group_for(spec, parent),
         hidden: cmd.is_hide_set(),
-        flags: flags_for(cmd, false),
-        persistentflags: flags_for(cmd, true),
+        parsing: parsing_for(cmd),
+        flags: flags_for(spec, cmd, false),
+        persistentflags: flags_for(spec, cmd, true),
+        exclusiveflags: exclusive_flags_for(spec, cmd, parent, &conflicts),
+        run: String::new(),
         documentation: Documentation {
             command: cmd.get_long_about().unwrap_or_default().to_string(),
-            flag: flag_documentation_for(cmd),
+            flag: flag_documentation_for(spec, cmd),
+            positional: positionals.documentation,
+            positionalany: positionals.any_documentation,
+            dash: dash.documentation,
+            dashany: dash.any_documentation,
         },
-        completion: {
-            let (positional, positionalany) = positional_completions_for(cmd);
-            Completion {
-                flag: flag_completions_for(cmd),
-                positional,
-                positionalany,
-                ..Default::default()
-            }
+        examples: match spec.extract_examples {
+            true => examples_for(cmd),
+            false => Map::new(),
+        },
+        completion: Completion {
+            flag: flag_completions_for(spec, cmd),
+            positional: positionals.completion,
+            positionalany: positionals.any_completion,
+            dash: dash.completion,
+            dashany: dash.any_completion,
         },
-        commands: cmd
-            .get_subcommands()
-            .filter(|c| !c.is_hide_set())
-            .map(command_for)
+        commands: sorted_subcommands(spec, cmd)
+            .into_iter()
+            .filter(|c| !(spec.strip_hidden && c.is_hide_set()))
+            .map(|c| command_for(spec, c, Some(cmd), &conflicts))
             .collect(),
     }
 }
 
-fn arg_sort_key(arg: &Arg) -> (Option<&str>, Option<char>) {
-    (arg.get_long(), arg.get_short())
+/// Fails unless `path` names a subcommand of `cmd`.
+fn check_path(cmd: &clap::Command, path: &str) -> Result<(), SpecError> {
+    path.split_whitespace()
+        .try_fold(cmd, |cmd, name| {
+            cmd.get_subcommands().find(|c| c.get_name() == name)
+        })
+        .map(|_| ())
+        .ok_or_else(|| SpecError::UnknownCommand(path.to_owned()))
 }
 
-fn sorted_args(cmd: &clap::Command) -> Vec<&Arg> {
-    let mut args: Vec<_> = cmd.get_arguments().collect();
-    args.sort_by_key(|a| arg_sort_key(a));
-    args
+/// Finds the subcommand at `path`, unless it was stripped.
+fn find_command<'a>(command: &'a mut Command, path: &str) -> Option<&'a mut Command> {
+    path.split_whitespace().try_fold(command, |command, name| {
+        command.commands.iter_mut().find(|c| c.name == name)
+    })
 }
 
-fn sorted_opts(cmd: &clap::Command) -> Vec<&Arg> {
-    let mut opts: Vec<_> = cmd.get_opts().collect();
-    opts.sort_by_key(|a| arg_sort_key(a));
-    opts
+fn examples_for(cmd: &clap::Command) -> Map<String, String> {
+    let help = cmd
+        .get_after_long_help()
+        .or_else(|| cmd.get_after_help())
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    let mut examples = Map::new();
+    let mut description = Vec::new();
+
+    let section = help
+        .lines()
+        .skip_while(|l| !l.trim().eq_ignore_ascii_case("examples:"))
+        .skip(1)
+        .take_while(|l| l.is_empty() || l.starts_with(char::is_whitespace));
+
+    for line in section.map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(comment) = line.strip_prefix('#') {
+            description.push(comment.trim());
+        } else {
+            let example = line.strip_prefix("$ ").unwrap_or(line);
+            examples.insert(example.to_owned(), description.join(" "));
+            description.clear();
+        }
+    }
+
+    examples
+}
+
+/// Subcommands are grouped by the subcommand help heading of their parent.
+///
+/// clap has no heading for individual subcommands ([`clap::Command::next_help_heading`]
+/// only applies to arguments), so per-command groups need an overlay.
+fn group_for(spec: &Spec, parent: Option<&clap::Command>) -> String {
+    let Some(parent) = parent else {
+        return String::new();
+    };
+
+    parent
+        .get_subcommand_help_heading()
+        .map(str::to_owned)
+        .or_else(|| spec.default_group.clone())
+        .unwrap_or_default()
 }
 
-fn flag_documentation_for(cmd: &clap::Command) -> Map<String, String> {
-    sorted_args(cmd)
+/// Derives how flags are parsed in relation to positionals.
+///
+/// A `trailing_var_arg` first positional stops flag parsing once it is reached. Without any
+/// flags, positionals accepting hyphen values take every argument as-is.
+fn parsing_for(cmd: &clap::Command) -> Parsing {
+    let pos: Vec<_> = sorted_positionals(cmd)
         .into_iter()
-        .filter(|a| !a.is_positional())
-        .filter_map(|arg| arg.get_long_help().map(|h| (arg_key(arg), h.to_string())))
-        .collect()
+        .filter(|p| !p.is_last_set())
+        .collect();
+
+    if cmd.get_arguments().all(Arg::is_positional)
+        && pos.iter().any(|p| p.is_allow_hyphen_values_set())
+    {
+        return Parsing::Disabled;
+    }
+
+    match pos.first() {
+        Some(first) if first.is_trailing_var_arg_set() => Parsing::NonInterspersed,
+        _ => Parsing::Interspersed,
+    }
 }
 
-fn flags_for(cmd: &clap::Command, persistent: bool) -> Map<String, FlagValue> {
-    let mut map = Map::new();
+/// A conflict between two flags, declared by the first one.
+type Conflict = (Id, Id);
 
-    for arg in sorted_args(cmd)
-        .into_iter()
+fn find_flag<'a>(cmd: &'a clap::Command, id: &Id) -> Option<&'a Arg> {
+    cmd.get_arguments()
         .filter(|a| !a.is_positional())
-        .filter(|a| a.is_global_set() == persistent)
+        .find(|a| a.get_id() == id)
+}
+
+/// Conflicts of global flags, accumulated down the command tree.
+///
+/// A global flag is resolved in the command declaring it, where clap ensures its conflicts
+/// exist. Conflicts with a group are expanded to its members.
+fn global_conflicts_for(
+    cmd: &clap::Command,
+    parent: Option<&clap::Command>,
+    inherited: &[Conflict],
+) -> Vec<Conflict> {
+    let mut conflicts = inherited.to_vec();
+
+    for arg in cmd
+        .get_arguments()
+        .filter(|a| !a.is_positional() && a.is_global_set())
+        .filter(|a| parent.is_none_or(|p| find_flag(p, a.get_id()).is_none()))
     {
-        let modifier = modifier_for(arg);
-        let help = arg.get_help().unwrap_or_default().to_string();
-        let signature = flag_signature(arg);
-        let value = flag_value_for(arg, &help);
+        // the lookup for global args doesn't know about groups
+        let local = arg.clone().global(false);
+        conflicts.extend(
+            cmd.get_arg_conflicts_with(&local)
+                .into_iter()
+                .filter(|other| !other.is_positional())
+                .map(|other| (arg.get_id().clone(), other.get_id().clone())),
+        );
+    }
 
-        map.insert(format!("{signature}{modifier}"), value.clone());
+    conflicts
+}
 
-        if let Some(aliases) = arg.get_visible_aliases() {
-            for alias in aliases {
-                map.insert(format!("--{alias}{modifier}"), value.clone());
-            }
-        }
+fn exclusive_flags_for(
+    spec: &Spec,
+    cmd: &clap::Command,
+    parent: Option<&clap::Command>,
+    conflicts: &[Conflict],
+) -> Vec<Vec<String>> {
+    let mut exclusive: Vec<Vec<&Arg>> = Vec::new();
 
-        if let Some(short_aliases) = arg.get_visible_short_aliases() {
-            for alias in short_aliases {
-                map.insert(format!("-{alias}{modifier}"), value.clone());
+    for arg in sorted_args(spec, cmd)
+        .into_iter()
+        .filter(|a| !a.is_positional() && !a.is_global_set())
+    {
+        for other in cmd.get_arg_conflicts_with(arg) {
+            if !other.is_positional() {
+                exclusive.push(vec![arg, other]);
             }
         }
+    }
 
-        let mut hidden_modifier = modifier.clone();
-        if !hidden_modifier.contains('&') {
-            hidden_modifier.push('&');
+    // an exclusive flag conflicts with every other flag
+    for arg in sorted_args(spec, cmd)
+        .into_iter()
+        .filter(|a| !a.is_positional() && a.is_exclusive_set())
+    {
+        for other in cmd.get_arguments().filter(|o| {
+            !o.is_positional()
+                && o.get_id() != arg.get_id()
+                && !matches!(
+                    o.get_action(),
+                    ArgAction::Help
+                        | ArgAction::HelpShort
+                        | ArgAction::HelpLong
+                        | ArgAction::Version
+                )
+        }) {
+            exclusive.push(vec![arg, other]);
         }
+    }
 
-        if let Some(aliases) = arg.get_aliases() {
-            for alias in aliases {
-                map.insert(format!("--{alias}{hidden_modifier}"), value.clone());
-            }
+    for (id, other) in conflicts {
+        let (Some(arg), Some(other)) = (find_flag(cmd, id), find_flag(cmd, other)) else {
+            continue;
+        };
+
+        // both flags inherited from the parent, which already lists the conflict
+        let inherited = other.is_global_set()
+            && parent.is_some_and(|p| {
+                find_flag(p, id).is_some() && find_flag(p, other.get_id()).is_some()
+            });
+        if !inherited {
+            exclusive.push(vec![arg, other]);
         }
+    }
 
-        if let Some(short_aliases) = arg.get_all_short_aliases() {
-            for alias in short_aliases {
-                let key = format!("-{alias}{modifier}");
-                if !map.contains_key(&key) {
-                    map.insert(format!("-{alias}{hidden_modifier}"), value.clone());
-                }
-            }
+    for group in cmd
+        .get_groups()
+        .filter(|g| !ArgGroup::clone(g).is_multiple())
+    {
+        let members: Vec<_> = group
+            .get_args()
+            .filter_map(|id| find_flag(cmd, id))
+            .collect();
+        if members.len() > 1 {
+            exclusive.push(members);
         }
     }
 
-    map
+    let mut exclusive: Vec<Vec<String>> = exclusive
+        .into_iter()
+        .map(|mut members| {
+            members.sort_by_key(|a| arg_sort_key(a));
+            members.dedup_by_key(|a| a.get_id());
+            members.into_iter().map(arg_key).collect()
+        })
+        .collect();
+    exclusive.sort();
+    exclusive.dedup();
+    exclusive
+}
+
+fn arg_sort_key(arg: &Arg) -> (Option<&str>, Option<char>) {
+    (arg.get_long(), arg.get_short())
+}
+
+fn sorted_args<'a>(spec: &Spec, cmd: &'a clap::Command) -> Vec<&'a Arg> {
+    sort_args(spec, cmd.get_arguments().collect())
+}
+
+fn sorted_opts<'a>(spec: &Spec, cmd: &'a clap::Command) -> Vec<&'a Arg> {
+    sort_args(spec, cmd.get_opts().collect())
+}
+
+fn sort_args<'a>(spec: &Spec, mut args: Vec<&'a Arg>) -> Vec<&'a Arg> {
+    match spec.order {
+        Order::Alphabetical => args.sort_by_key(|a| arg_sort_key(a)),
+        Order::Declaration => {}
+        Order::DisplayOrder => args.sort_by_key(|a| (a.get_display_order(), arg_sort_key(a))),
+    }
+    args
+}
+
+fn sorted_subcommands<'a>(spec: &Spec, cmd: &'a clap::Command) -> Vec<&'a clap::Command> {
+    let mut subcommands: Vec<_> = cmd.get_subcommands().collect();
+    if spec.order == Order::DisplayOrder {
+        subcommands.sort_by_key(|c| (c.get_display_order(), c.get_name()));
+    }
+    subcommands
+}
+
+fn flag_documentation_for(spec: &Spec, cmd: &clap::Command) -> Map<String, String> {
+    sorted_args(spec, cmd)
+        .into_iter()
+        .filter(|a| !a.is_positional())
+        .filter_map(|arg| {
+            let help = arg.get_long_help().map(ToString::to_string);
+            let missing = default_missing_for(cmd, arg).map(|value| {
+                let name = match (arg.get_long(), arg.get_short()) {
+                    (Some(long), _) => format!("--{long}"),
+                    (None, Some(short)) => format!("-{short}"),
+                    (None, None) => arg.get_id().to_string(),
+                };
+                format!("`{name}` without a value means `{value}`.")
+            });
+            let documentation = match (help, missing) {
+                (Some(help), Some(missing)) => format!("{help}\n\n{missing}"),
+                (Some(doc), None) | (None, Some(doc)) => doc,
+                (None, None) => return None,
+            };
+            Some((arg_key(arg), documentation))
+        })
+        .collect()
 }
 
-fn flag_signature(arg: &Arg) -> String {
-    match (arg.get_long(), arg.get_short()) {
-        (Some(l), Some(s)) => format!("-{s}, --{l}"),
-        (Some(l), None) => format!("--{l}"),
-        (None, Some(s)) => format!("-{s}"),
-        _ => unreachable!("clap arg without identifier"),
+/// Whether the value of the flag can be omitted, e.g. `--color` with `num_args(0..=1)`.
+fn is_optional(arg: &Arg) -> bool {
+    arg.get_num_args()
+        .is_some_and(|range| range.min_values() == 0 && range.takes_values())
+}
+
+/// The value used when an optional-value flag is given without one.
+///
+/// clap has no getter for `default_missing_value`, so the flag is parsed without a value.
+fn default_missing_for(cmd: &clap::Command, arg: &Arg) -> Option<String> {
+    if !arg.get_action().takes_values() || !is_optional(arg) {
+        return None;
+    }
+
+    let flag = match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => format!("--{long}"),
+        (None, Some(short)) => format!("-{short}"),
+        (None, None) => return None,
+    };
+    let matches = cmd
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from([cmd.get_name(), &flag])
+        .ok()?;
+    if matches.value_source(arg.get_id().as_str()) != Some(ValueSource::CommandLine) {
+        return None;
+    }
+
+    let values: Vec<_> = matches
+        .try_get_raw(arg.get_id().as_str())
+        .ok()??
+        .map(|v| v.to_string_lossy().into_owned())
+        .collect();
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+fn flags_for(spec: &Spec, cmd: &clap::Command, persistent: bool) -> Vec<Flag> {
+    sorted_args(spec, cmd)
+        .into_iter()
+        .filter(|a| !a.is_positional())
+        .filter(|a| a.is_global_set() == persistent)
+        .map(flag_for)
+        .collect()
+}
+
+fn flag_for(arg: &Arg) -> Flag {
+    let takes_values = arg.get_action().takes_values();
+
+    let aliases: Vec<_> = arg
+        .get_visible_aliases()
+        .into_iter()
+        .flatten()
+        .map(|a| Alias::Long(a.to_owned()))
+        .chain(
+            arg.get_visible_short_aliases()
+                .into_iter()
+                .flatten()
+                .map(Alias::Short),
+        )
+        .collect();
+
+    let hidden_aliases = arg
+        .get_aliases()
+        .into_iter()
+        .flatten()
+        .map(|a| Alias::Long(a.to_owned()))
+        .chain(
+            arg.get_all_short_aliases()
+                .into_iter()
+                .flatten()
+                .map(Alias::Short)
+                .filter(|a| !aliases.contains(a)),
+        )
+        .collect();
+
+    Flag {
+        short: arg.get_short(),
+        long: arg.get_long().map(str::to_owned),
+        aliases,
+        hidden_aliases,
+        description: arg.get_help().unwrap_or_default().to_string(),
+        hidden: arg.is_hide_set(),
+        required: takes_values && arg.is_required_set(),
+        value: match (
+            takes_values,
+            arg.is_require_equals_set() || is_optional(arg),
+        ) {
+            (false, _) => ValueKind::None,
+            (true, true) => ValueKind::Optional,
+            (true, false) => ValueKind::Required,
+        },
+        repeatable: matches!(arg.get_action(), ArgAction::Append | ArgAction::Count),
+        nargs: nargs_for(arg),
+        default: default_for(arg),
+        tag: arg.get_help_heading().unwrap_or_default().to_owned(),
     }
 }
 
@@ -274,40 +1092,201 @@ fn arg_key(arg: &Arg) -> String {
         .unwrap_or_default()
 }
 
-fn positional_completions_for(cmd: &clap::Command) -> (Vec<Vec<String>>, Vec<String>) {
+fn sorted_positionals(cmd: &clap::Command) -> Vec<&Arg> {
     let mut pos: Vec<_> = cmd.get_positionals().collect();
     pos.sort_by_key(|a| a.get_index());
+    pos
+}
 
-    let positionalany = pos
-        .last()
-        .filter(|p| p.get_num_args().unwrap_or_default().max_values() == usize::MAX)
-        .map(|p| completion_for(p))
-        .unwrap_or_default();
+/// Whether the positional takes a fixed number of values, e.g. `num_args(2)`.
+fn is_fixed(arg: &Arg) -> bool {
+    let range = arg.get_num_args().unwrap_or_default();
+    range.min_values() == range.max_values()
+}
+
+/// Number of words a positional with a fixed number of values consumes.
+fn slot_count(arg: &Arg) -> usize {
+    arg.get_num_args().map_or(1, |r| r.max_values().max(1))
+}
+
+/// Completion and documentation of positionals, slot by slot.
+#[derive(Default)]
+struct Slots {
+    completion: Vec<Vec<String>>,
+    documentation: Vec<String>,
+    any_completion: Vec<String>,
+    any_documentation: String,
+}
+
+/// Positionals that can be given before `--`.
+///
+/// `last(true)` positionals are only reachable after `--` and thus excluded.
+fn positional_slots_for(spec: &Spec, cmd: &clap::Command) -> Slots {
+    let pos: Vec<_> = sorted_positionals(cmd)
+        .into_iter()
+        .filter(|p| !p.is_last_set())
+        .collect();
+
+    if pos.is_empty() {
+        if let Some(parser) = cmd.get_external_subcommand_value_parser() {
+            return external_slots_for(spec, parser);
+        }
+    }
+
+    slots_for(spec, pos)
+}
 
-    let positional = pos
+/// An external subcommand occupies the first positional, followed by its arguments.
+///
+/// The arguments are only completed if the value parser of the external subcommand
+/// has possible values.
+fn external_slots_for(spec: &Spec, parser: &ValueParser) -> Slots {
+    let name = spec
+        .external_subcommands
+        .clone()
+        .unwrap_or_else(|| vec!["$executables".to_owned()]);
+
+    let args = parser
+        .possible_values()
         .into_iter()
-        .filter(|p| p.get_num_args().unwrap_or_default().max_values() != usize::MAX)
-        .map(completion_for)
-        .filter(|v: &Vec<_>| !v.is_empty())
+        .flatten()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| value_for(&v))
         .collect();
 
-    (positional, positionalany)
+    Slots {
+        completion: vec![name],
+        any_completion: args,
+        ..Default::default()
+    }
+}
+
+/// Positionals that are completed after `--`.
+///
+/// `last(true)` positionals always follow `--`. A `trailing_var_arg` positional
+/// swallows everything after `--` as well, so it is mirrored into `dashany`.
+fn dash_slots_for(spec: &Spec, cmd: &clap::Command) -> Slots {
+    let last: Vec<_> = sorted_positionals(cmd)
+        .into_iter()
+        .filter(|p| p.is_last_set())
+        .collect();
+
+    let mut slots = slots_for(spec, last);
+
+    if slots.any_completion.is_empty() && slots.any_documentation.is_empty() {
+        if let Some(trailing) = sorted_positionals(cmd)
+            .into_iter()
+            .rfind(|p| !p.is_last_set())
+            .filter(|p| p.is_trailing_var_arg_set())
+        {
+            slots.any_completion = completion_for(spec, trailing);
+            slots.any_documentation = positional_help_for(trailing);
+        }
+    }
+
+    slots
+}
+
+/// Maps positionals to fixed slots and any further words, with completion and documentation.
+///
+/// Positionals up to the first one with a variable number of values get a slot per value.
+/// Carapace can't express slots that follow it (see [`Spec`]), so it and all positionals
+/// after it share `any`. A positional spanning several slots is documented per slot with
+/// its value names.
+fn slots_for(spec: &Spec, pos: Vec<&Arg>) -> Slots {
+    let variable = pos.iter().position(|p| !is_fixed(p)).unwrap_or(pos.len());
+    let (fixed, rest) = pos.split_at(variable);
+
+    let mut slots = Slots::default();
+
+    for p in fixed {
+        let completion = completion_for(spec, p);
+        let help = positional_help_for(p);
+        let count = slot_count(p);
+        // value names only tell the slots of a single positional apart
+        let names = match count {
+            1 => &[][..],
+            _ => p.get_value_names().unwrap_or_default(),
+        };
+
+        for i in 0..count {
+            let name = match names {
+                [] => String::new(),
+                [name] => name.to_string(),
+                names => names.get(i).map(ToString::to_string).unwrap_or_default(),
+            };
+            slots.completion.push(completion.clone());
+            slots
+                .documentation
+                .push(match (name.is_empty(), help.is_empty()) {
+                    (false, false) => format!("{name}: {help}"),
+                    (_, false) => help.clone(),
+                    (_, true) => name,
+                });
+        }
+    }
+
+    for p in rest {
+        for action in completion_for(spec, p) {
+            if !slots.any_completion.contains(&action) {
+                slots.any_completion.push(action);
+            }
+        }
+    }
+    slots.any_documentation = rest
+        .iter()
+        .map(|p| positional_help_for(p))
+        .filter(|h| !h.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    // the bridge takes the first word as command name, so skip the preceding slots
+    if !slots.completion.is_empty()
+        && rest
+            .first()
+            .is_some_and(|p| p.get_value_hint() == CommandWithArguments)
+    {
+        let shift = format!("$shift({})", slots.completion.len());
+        slots.any_completion.insert(0, shift);
+    }
+
+    // Empty slots keep the following ones aligned with their position, trailing ones
+    // are only needed to keep `any` from applying too early.
+    if slots.any_completion.is_empty() {
+        while slots.completion.last().is_some_and(Vec::is_empty) {
+            slots.completion.pop();
+        }
+    }
+    if slots.any_documentation.is_empty() {
+        while slots.documentation.last().is_some_and(String::is_empty) {
+            slots.documentation.pop();
+        }
+    }
+
+    slots
+}
+
+fn positional_help_for(arg: &Arg) -> String {
+    arg.get_long_help()
+        .or_else(|| arg.get_help())
+        .map(ToString::to_string)
+        .unwrap_or_default()
 }
 
-fn completion_for(arg: &Arg) -> Vec<String> {
-    action_for(arg.get_value_hint())
+fn completion_for(spec: &Spec, arg: &Arg) -> Vec<String> {
+    spec.action_for(arg)
         .into_iter()
         .chain(values_for(arg))
         .collect()
 }
 
-fn flag_completions_for(cmd: &clap::Command) -> Map<String, Vec<String>> {
+fn flag_completions_for(spec: &Spec, cmd: &clap::Command) -> Map<String, Vec<String>> {
     let mut map = Map::new();
 
-    for opt in sorted_opts(cmd).into_iter() {
+    for opt in sorted_opts(spec, cmd).into_iter() {
         let name = arg_key(opt);
 
-        let actions: Vec<_> = completion_for(opt);
+        let actions: Vec<_> = completion_for(spec, opt);
 
         if actions.is_empty() {
             continue;
@@ -331,57 +1310,24 @@ fn flag_completions_for(cmd: &clap::Command) -> Map<String, Vec<String>> {
     map
 }
 
+/// Possible values of the arg, except hidden ones or all of them with `hide_possible_values`.
 fn values_for(arg: &Arg) -> Vec<String> {
+    if arg.is_hide_possible_values_set() {
+        return Vec::new();
+    }
     generator::utils::possible_values(arg)
         .into_iter()
         .flatten()
-        .map(|v| {
-            v.get_help()
-                .map(|h| format!("{}\t{}", v.get_name(), h))
-                .unwrap_or_else(|| v.get_name().to_owned())
-        })
+        .filter(|v| !v.is_hide_set())
+        .map(|v| value_for(&v))
         .collect()
 }
 
-fn modifier_for(arg: &Arg) -> String {
-    let mut m = String::new();
-
-    if arg.is_hide_set() {
-        m.push('&');
-    }
-
-    if arg.get_action().takes_values() {
-        if arg.is_required_set() {
-            m.push('!');
-        }
-
-        if arg.is_require_equals_set() {
-            m.push('?');
-        } else {
-            m.push('=');
-        }
-    }
-
-    if matches!(arg.get_action(), ArgAction::Append | ArgAction::Count) {
-        m.push('*');
-    }
-
-    m
-}
-
-fn flag_value_for(arg: &Arg, description: &str) -> FlagValue {
-    let nargs = nargs_for(arg);
-    let default = default_for(arg);
-
-    if nargs != 0 || !default.is_empty() {
-        FlagValue::Extended(ExtendedFlag {
-            description: description.to_string(),
-            nargs,
-            default,
-        })
-    } else {
-        FlagValue::Plain(description.to_string())
-    }
+fn value_for(value: &PossibleValue) -> String {
+    value
+        .get_help()
+        .map(|h| format!("{}\t{}", value.get_name(), h))
+        .unwrap_or_else(|| value.get_name().to_owned())
 }
 
 fn nargs_for(arg: &Arg) -> i64 {
@@ -409,13 +1355,18 @@ fn default_for(arg: &Arg) -> String {
         .join(",")
 }
 
+/// The builtin mapping of a [`ValueHint`] to carapace macros, see [`Spec::hint`].
 fn action_for(hint: ValueHint) -> Vec<String> {
     let actions = match hint {
         AnyPath | FilePath | ExecutablePath => vec!["$files"],
         DirPath => vec!["$directories"],
         CommandName | CommandString => vec!["$executables", "$files"],
+        CommandWithArguments => vec!["$carapace.bridge.CarapaceBin"],
         Username => vec!["$carapace.os.Users"],
         Hostname => vec!["$carapace.net.Hosts"],
+        Url => vec!["file://", "ftp://", "http://", "https://", "$nospace(/)"],
+        EmailAddress => vec!["$carapace.os.Users", "$suffix(@)", "$nospace(@)"],
+        Other | Unknown => vec![],
         _ => vec![],
     };
 
@@ -0,0 +1,59 @@
+use std::{error, fmt, io};
+
+/// Errors raised while generating or parsing a spec.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SpecError {
+    /// The spec could not be (de)serialized as YAML.
+    Yaml(yaml_serde::Error),
+    /// The spec could not be (de)serialized as JSON.
+    Json(serde_json::Error),
+    /// The spec could not be read or written.
+    Io(io::Error),
+    /// A path given to the [`Spec`](crate::Spec) does not name a subcommand.
+    UnknownCommand(String),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Yaml(e) => write!(f, "YAML (de)serialization failed: {e}"),
+            SpecError::Json(e) => write!(f, "JSON (de)serialization failed: {e}"),
+            SpecError::Io(e) => write!(f, "I/O failed: {e}"),
+            SpecError::UnknownCommand(path) => write!(f, "unknown command `{path}`"),
+        }
+    }
+}
+
+impl error::Error for SpecError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SpecError::Yaml(e) => Some(e),
+            SpecError::Json(e) => Some(e),
+            SpecError::Io(e) => Some(e),
+            SpecError::UnknownCommand(_) => None,
+        }
+    }
+}
+
+impl From<yaml_serde::Error> for SpecError {
+    fn from(e: yaml_serde::Error) -> Self {
+        SpecError::Yaml(e)
+    }
+}
+
+impl From<serde_json::Error> for SpecError {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            SpecError::Io(e.into())
+        } else {
+            SpecError::Json(e)
+        }
+    }
+}
+
+impl From<io::Error> for SpecError {
+    fn from(e: io::Error) -> Self {
+        SpecError::Io(e)
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{ExtendedFlag, FlagValue};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{error, fmt, str::FromStr};
+
+/// Whether a flag takes a value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    /// The flag is a switch (no modifier).
+    #[default]
+    None,
+    /// The flag requires a value (`=`).
+    Required,
+    /// The flag takes an optional value that must be attached with `=` (`?`).
+    Optional,
+}
+
+/// A flag key like `-c, --config!=*` split into its parts.
+///
+/// | modifier | meaning                 |
+/// |----------|-------------------------|
+/// | `&`      | hidden                  |
+/// | `!`      | required                |
+/// | `=`      | takes a value           |
+/// | `?`      | takes an optional value |
+/// | `*`      | repeatable              |
+///
+/// Only POSIX style flags are supported, i.e. a single character shorthand and/or a
+/// longhand prefixed with `--`.
+///
+/// ```
+/// # use carapace_spec_clap::{FlagKey, ValueKind};
+/// let key: FlagKey = "-c, --config!=*".parse().unwrap();
+/// assert_eq!(key.short, Some('c'));
+/// assert_eq!(key.long.as_deref(), Some("config"));
+/// assert!(key.required && key.repeatable);
+/// assert_eq!(key.value, ValueKind::Required);
+/// assert_eq!(key.to_string(), "-c, --config!=*");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FlagKey {
+    pub short: Option<char>,
+    pub long: Option<String>,
+    pub hidden: bool,
+    pub required: bool,
+    pub value: ValueKind,
+    pub repeatable: bool,
+}
+
+impl fmt::Display for FlagKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.short, &self.long) {
+            (Some(s), Some(l)) => write!(f, "-{s}, --{l}")?,
+            (Some(s), None) => write!(f, "-{s}")?,
+            (None, Some(l)) => write!(f, "--{l}")?,
+            (None, None) => {}
+        }
+
+        if self.hidden {
+            f.write_str("&")?;
+        }
+        if self.required {
+            f.write_str("!")?;
+        }
+        match self.value {
+            ValueKind::None => {}
+            ValueKind::Required => f.write_str("=")?,
+            ValueKind::Optional => f.write_str("?")?,
+        }
+        if self.repeatable {
+            f.write_str("*")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when a flag key can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFlagKeyError {
+    key: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseFlagKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid flag `{}`: {}", self.key, self.reason)
+    }
+}
+
+impl error::Error for ParseFlagKeyError {}
+
+impl FromStr for FlagKey {
+    type Err = ParseFlagKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason| ParseFlagKeyError {
+            key: s.to_owned(),
+            reason,
+        };
+
+        let names = s.trim_end_matches(['&', '!', '=', '?', '*']);
+        let mut key = FlagKey::default();
+
+        for c in s[names.len()..].chars() {
+            let set = match c {
+                '&' => &mut key.hidden,
+                '!' => &mut key.required,
+                '*' => &mut key.repeatable,
+                '=' | '?' if key.value != ValueKind::None => {
+                    return Err(err("conflicting value modifiers"))
+                }
+                '=' => {
+                    key.value = ValueKind::Required;
+                    continue;
+                }
+                '?' => {
+                    key.value = ValueKind::Optional;
+                    continue;
+                }
+                _ => unreachable!("trimmed modifier"),
+            };
+            if *set {
+                return Err(err("duplicate modifier"));
+            }
+            *set = true;
+        }
+
+        let (short, long) = match names.split_once(", ") {
+            Some((short, long)) => (Some(short), Some(long)),
+            None if names.starts_with("--") => (None, Some(names)),
+            None => (Some(names), None),
+        };
+
+        if let Some(short) = short {
+            let mut chars = short.chars();
+            key.short = match (chars.next(), chars.next(), chars.next()) {
+                (Some('-'), Some(c), None) if c != '-' => Some(c),
+                _ => {
+                    return Err(err(
+                        "shorthand must be a single character prefixed with `-`",
+                    ))
+                }
+            };
+        }
+
+        if let Some(long) = long {
+            key.long = match long.strip_prefix("--") {
+                Some(l) if !l.is_empty() && !l.starts_with('-') && !l.contains(' ') => {
+                    Some(l.to_owned())
+                }
+                _ => return Err(err("longhand must be prefixed with `--`")),
+            };
+        }
+
+        Ok(key)
+    }
+}
+
+impl Serialize for FlagKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FlagKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// An alternative name of a flag.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Alias {
+    Short(char),
+    Long(String),
+}
+
+/// A flag with its aliases and value.
+///
+/// Serialized as one entry per name, e.g. `-c, --config!=*: description`, followed by
+/// an entry for each alias. Parsing yields a flag per entry, so aliases are not
+/// recovered from a spec.
+///
+/// ```
+/// # use carapace_spec_clap::{Alias, Flag, ValueKind};
+/// let flag = Flag {
+///     short: Some('c'),
+///     long: Some("config".to_owned()),
+///     aliases: vec![Alias::Long("conf".to_owned())],
+///     value: ValueKind::Required,
+///     repeatable: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(flag.key().to_string(), "-c, --config=*");
+/// assert_eq!(flag.keys().last().unwrap().to_string(), "--conf=*");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Flag {
+    pub short: Option<char>,
+    pub long: Option<String>,
+    /// Aliases listed in completion.
+    pub aliases: Vec<Alias>,
+    /// Aliases that are completed only when typed explicitly.
+    pub hidden_aliases: Vec<Alias>,
+    pub description: String,
+    pub hidden: bool,
+    pub required: bool,
+    pub value: ValueKind,
+    pub repeatable: bool,
+    /// Number of values, `-1` for any.
+    pub nargs: i64,
+    pub default: String,
+    /// Groups the flag in completion, taken from the help heading.
+    pub tag: String,
+}
+
+impl Flag {
+    /// The key of the flag itself.
+    pub fn key(&self) -> FlagKey {
+        FlagKey {
+            short: self.short,
+            long: self.long.clone(),
+            hidden: self.hidden,
+            required: self.required,
+            value: self.value,
+            repeatable: self.repeatable,
+        }
+    }
+
+    /// The keys of the flag and its aliases, in serialization order.
+    pub fn keys(&self) -> Vec<FlagKey> {
+        let key = self.key();
+        let alias = |alias: &Alias, hidden: bool| {
+            let (short, long) = match alias {
+                Alias::Short(s) => (Some(*s), None),
+                Alias::Long(l) => (None, Some(l.clone())),
+            };
+            FlagKey {
+                short,
+                long,
+                hidden: key.hidden || hidden,
+                ..key.clone()
+            }
+        };
+
+        let aliases = self.aliases.iter().map(|a| alias(a, false));
+        let hidden_aliases = self.hidden_aliases.iter().map(|a| alias(a, true));
+
+        std::iter::once(key.clone())
+            .chain(aliases)
+            .chain(hidden_aliases)
+            .collect()
+    }
+
+    fn flag_value(&self) -> FlagValue {
+        if self.nargs != 0 || !self.default.is_empty() || !self.tag.is_empty() {
+            FlagValue::Extended(ExtendedFlag {
+                description: self.description.clone(),
+                nargs: self.nargs,
+                default: self.default.clone(),
+                tag: self.tag.clone(),
+            })
+        } else {
+            FlagValue::Plain(self.description.clone())
+        }
+    }
+
+    fn from_entry(key: FlagKey, value: FlagValue) -> Self {
+        let (description, nargs, default, tag) = match value {
+            FlagValue::Plain(description) => (description, 0, String::new(), String::new()),
+            FlagValue::Extended(e) => (e.description, e.nargs, e.default, e.tag),
+        };
+
+        Flag {
+            short: key.short,
+            long: key.long,
+            description,
+            hidden: key.hidden,
+            required: key.required,
+            value: key.value,
+            repeatable: key.repeatable,
+            nargs,
+            default,
+            tag,
+            ..Default::default()
+        }
+    }
+}
+
+/// (De)serializes flags as a map of flag keys.
+pub(crate) mod map {
+    use super::{Flag, FlagKey};
+    use crate::FlagValue;
+    use indexmap::IndexMap as Map;
+    use serde::{ser::SerializeMap, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(flags: &[Flag], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for flag in flags {
+            let value = flag.flag_value();
+            for key in flag.keys() {
+                map.serialize_entry(&key, &value)?;
+            }
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Flag>, D::Error> {
+        let map = Map::<FlagKey, FlagValue>::deserialize(deserializer)?;
+        Ok(map
+            .into_iter()
+            .map(|(key, value)| Flag::from_entry(key, value))
+            .collect())
+    }
+}
@@ -1,2 +1,8 @@
 mod carapace_spec;
-pub use carapace_spec::Spec;
+mod error;
+mod flag;
+pub use carapace_spec::{
+    Command, Completion, Documentation, ExtendedFlag, FlagValue, Order, Parsing, Spec, SpecJson,
+};
+pub use error::SpecError;
+pub use flag::{Alias, Flag, FlagKey, ParseFlagKeyError, ValueKind};
@@ -6,7 +6,7 @@ fn basic() {
     let cmd = common::basic_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/basic.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
         cmd,
         name,
     );
@@ -18,7 +18,19 @@ fn feature_sample() {
     let cmd = common::feature_sample_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/feature_sample.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn feature_sample_json() {
+    let name = "feature_sample";
+    let cmd = common::feature_sample_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/feature_sample.json"],
+        carapace_spec_clap::SpecJson::default(),
         cmd,
         name,
     );
@@ -30,7 +42,19 @@ fn special_commands() {
     let cmd = common::special_commands_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/special_commands.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn special_commands_strip_hidden() {
+    let name = "special_commands";
+    let cmd = common::special_commands_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/special_commands_strip_hidden.yaml"],
+        carapace_spec_clap::Spec::new().strip_hidden(true),
         cmd,
         name,
     );
@@ -42,7 +66,7 @@ fn quoting() {
     let cmd = common::quoting_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/quoting.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
         cmd,
         name,
     );
@@ -54,7 +78,7 @@ fn aliases() {
     let cmd = common::aliases_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/aliases.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
         cmd,
         name,
     );
@@ -66,7 +90,7 @@ fn sub_subcommands() {
     let cmd = common::sub_subcommands_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/sub_subcommands.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
         cmd,
         name,
     );
@@ -78,7 +102,7 @@ fn value_hint() {
     let cmd = common::value_hint_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/value_hint.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
         cmd,
         name,
     );
@@ -90,7 +114,475 @@ fn extended_notation() {
     let cmd = common::extended_notation_command(name);
     common::assert_matches(
         snapbox::file!["snapshots/extended_notation.yaml"],
-        carapace_spec_clap::Spec,
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn dash() {
+    let name = "dash";
+    let cmd = common::dash_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/dash.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn exclusive_flags() {
+    let name = "exclusive_flags";
+    let cmd = common::exclusive_flags_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/exclusive_flags.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn value_hint_override() {
+    let name = "value_hint_override";
+    let cmd = common::value_hint_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/value_hint_override.yaml"],
+        carapace_spec_clap::Spec::new()
+            .hint(clap::ValueHint::FilePath, ["$files([.toml, .yaml])"])
+            .hint(clap::ValueHint::Username, ["$carapace.tools.custom.Users"])
+            .hint(clap::ValueHint::Url, Vec::<String>::new()),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn custom_actions() {
+    let name = "custom_actions";
+    let cmd = common::custom_actions_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/custom_actions.yaml"],
+        carapace_spec_clap::Spec::new()
+            .action("branch", ["$carapace.tools.git.LocalBranches"])
+            .action("config", ["$files([.toml])"])
+            .action("format", ["$carapace.tools.custom.Formats"])
+            .action("commit", ["$carapace.tools.git.Refs"])
+            .action("path", ["$carapace.tools.git.Changes"]),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn external_subcommands() {
+    let name = "external_subcommands";
+    let cmd = common::external_subcommands_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/external_subcommands.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn external_subcommands_custom() {
+    let name = "external_subcommands";
+    let cmd = common::external_subcommands_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/external_subcommands_custom.yaml"],
+        carapace_spec_clap::Spec::new().external_subcommands(["$carapace.tools.custom.Plugins"]),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn parsing() {
+    let name = "parsing";
+    let cmd = common::parsing_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/parsing.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+struct BrokenPipe;
+
+impl std::io::Write for BrokenPipe {
+    fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn try_generate_io_error() {
+    let cmd = common::basic_command("basic");
+
+    let err = carapace_spec_clap::Spec::default()
+        .try_generate(&cmd, &mut BrokenPipe)
+        .unwrap_err();
+    assert!(matches!(err, carapace_spec_clap::SpecError::Io(_)));
+
+    let err = carapace_spec_clap::SpecJson::default()
+        .try_generate(&cmd, &mut BrokenPipe)
+        .unwrap_err();
+    assert!(matches!(err, carapace_spec_clap::SpecError::Io(_)));
+}
+
+#[test]
+fn try_generate_unbuilt() {
+    let cmd = common::feature_sample_command("feature_sample");
+
+    let mut buf = vec![];
+    carapace_spec_clap::Spec::default()
+        .try_generate(&cmd, &mut buf)
+        .unwrap();
+    snapbox::assert_data_eq!(buf, snapbox::file!["snapshots/feature_sample.yaml"]);
+
+    let mut buf = vec![];
+    carapace_spec_clap::SpecJson::default()
+        .try_generate(&cmd, &mut buf)
+        .unwrap();
+    snapbox::assert_data_eq!(buf, snapbox::file!["snapshots/feature_sample.json"]);
+}
+
+#[test]
+fn examples_unknown_command() {
+    let cmd = common::examples_command("examples");
+
+    let err = carapace_spec_clap::Spec::new()
+        .examples("missing", [("examples missing", "")])
+        .try_generate(&cmd, &mut vec![])
+        .unwrap_err();
+    assert!(
+        matches!(err, carapace_spec_clap::SpecError::UnknownCommand(path) if path == "missing")
+    );
+}
+
+#[test]
+fn shortcuts_unknown_command() {
+    let cmd = common::shortcuts_command("shortcuts");
+
+    let err = carapace_spec_clap::Spec::new()
+        .shortcut("log lg", ["shortcuts", "log"])
+        .try_generate(&cmd, &mut vec![])
+        .unwrap_err();
+    assert!(matches!(err, carapace_spec_clap::SpecError::UnknownCommand(path) if path == "log lg"));
+
+    let err = carapace_spec_clap::Spec::new()
+        .shortcut("", ["shortcuts", "log"])
+        .try_generate(&cmd, &mut vec![])
+        .unwrap_err();
+    assert!(matches!(err, carapace_spec_clap::SpecError::UnknownCommand(path) if path.is_empty()));
+}
+
+#[test]
+fn round_trip() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let content = std::fs::read_to_string(&path).unwrap();
+
+        let mut buf = vec![];
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") => {
+                let command = carapace_spec_clap::Spec::parse_yaml(&content).unwrap();
+                carapace_spec_clap::Spec::write(&command, &mut buf).unwrap();
+            }
+            Some("json") => {
+                let command = carapace_spec_clap::SpecJson::parse_json(&content).unwrap();
+                carapace_spec_clap::SpecJson::write(&command, &mut buf).unwrap();
+            }
+            _ => continue,
+        }
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            content,
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn invalid_flag_key() {
+    let err = carapace_spec_clap::Spec::parse_yaml("name: invalid\nflags:\n  -config=: ''\n")
+        .unwrap_err();
+    assert!(matches!(err, carapace_spec_clap::SpecError::Yaml(_)));
+
+    for key in [
+        "-c, --config=?",
+        "--config**",
+        "config",
+        "-c, -config",
+        "---",
+    ] {
+        assert!(key.parse::<carapace_spec_clap::FlagKey>().is_err(), "{key}");
+    }
+}
+
+#[test]
+fn overlay() {
+    let name = "overlay";
+    let cmd = common::feature_sample_command(name);
+    let spec = carapace_spec_clap::Spec::default()
+        .overlay_yaml(
+            r#"
+name: overlay
+flags:
+  -c, --config=*: overridden config flag
+  --extra: flag unknown to clap
+completion:
+  positional:
+  - []
+  - [$carapace.tools.custom.Choices]
+commands:
+- name: test
+  description: overridden description
+  completion:
+    flag:
+      case: [$carapace.tools.custom.Cases]
+- name: extra
+  description: subcommand unknown to clap
+"#,
+        )
+        .unwrap()
+        .overlay_yaml(
+            r#"
+name: overlay
+exclusiveflags:
+- [config, extra]
+"#,
+        )
+        .unwrap();
+
+    common::assert_matches(snapbox::file!["snapshots/overlay.yaml"], spec, cmd, name);
+}
+
+#[test]
+fn overlay_aliases() {
+    let name = "aliases";
+    let cmd = common::aliases_command(name);
+    let spec = carapace_spec_clap::Spec::default()
+        .overlay_yaml(
+            r#"
+name: aliases
+flags:
+  --flg: overridden by visible alias
+  --option=*: overridden by longhand
+"#,
+        )
+        .unwrap();
+
+    common::assert_matches(
+        snapbox::file!["snapshots/overlay_aliases.yaml"],
+        spec,
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn groups() {
+    let name = "groups";
+    let cmd = common::groups_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/groups.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn groups_default() {
+    let name = "groups";
+    let cmd = common::groups_command(name);
+    let spec = carapace_spec_clap::Spec::new()
+        .default_group("Other Commands")
+        .overlay_yaml(
+            r#"
+name: groups
+commands:
+- name: remote
+  group: Plumbing Commands
+"#,
+        )
+        .unwrap();
+    common::assert_matches(
+        snapbox::file!["snapshots/groups_default.yaml"],
+        spec,
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn help_headings() {
+    let name = "help_headings";
+    let cmd = common::help_headings_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/help_headings.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn order_alphabetical() {
+    let name = "order";
+    let cmd = common::order_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/order_alphabetical.yaml"],
+        carapace_spec_clap::Spec::new().order(carapace_spec_clap::Order::Alphabetical),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn order_declaration() {
+    let name = "order";
+    let cmd = common::order_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/order_declaration.yaml"],
+        carapace_spec_clap::Spec::new().order(carapace_spec_clap::Order::Declaration),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn order_display_order() {
+    let name = "order";
+    let cmd = common::order_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/order_display_order.yaml"],
+        carapace_spec_clap::Spec::new().order(carapace_spec_clap::Order::DisplayOrder),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn examples() {
+    let name = "examples";
+    let cmd = common::examples_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/examples.yaml"],
+        carapace_spec_clap::Spec::new()
+            .extract_examples(true)
+            .examples("", [("examples --help", "show help")])
+            .examples("sub", [("examples sub --quiet", "")]),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn shortcuts() {
+    let name = "shortcuts";
+    let cmd = common::shortcuts_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/shortcuts.yaml"],
+        carapace_spec_clap::Spec::new()
+            .shortcut("lg", [name, "log", "--format=%h, %s", "--grep=[fix]"]),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn optional_values() {
+    let name = "optional-values";
+    let cmd = common::optional_values_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/optional_values.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn positional_slots() {
+    let name = "positional-slots";
+    let cmd = common::positional_slots_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/positional_slots.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn positional_arity() {
+    let name = "positional-arity";
+    let cmd = common::positional_arity_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/positional_arity.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn variadic_positionals() {
+    let name = "variadic-positionals";
+    let cmd = common::variadic_positionals_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/variadic_positionals.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn positional_documentation() {
+    let name = "positional-documentation";
+    let cmd = common::positional_documentation_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/positional_documentation.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn hidden_values() {
+    let name = "hidden-values";
+    let cmd = common::hidden_values_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/hidden_values.yaml"],
+        carapace_spec_clap::Spec::default(),
+        cmd,
+        name,
+    );
+}
+
+#[test]
+fn bridge_shift() {
+    let name = "bridge-shift";
+    let cmd = common::bridge_shift_command(name);
+    common::assert_matches(
+        snapbox::file!["snapshots/bridge_shift.yaml"],
+        carapace_spec_clap::Spec::default(),
         cmd,
         name,
     );
@@ -299,6 +299,533 @@ pub fn extended_notation_command(name: &'static str) -> clap::Command {
         )
 }
 
+pub fn dash_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests completion after dash")
+        .subcommand(
+            clap::Command::new("diff")
+                .about("last positional")
+                .arg(clap::Arg::new("base").value_parser(["HEAD", "main"]))
+                .arg(
+                    clap::Arg::new("path")
+                        .last(true)
+                        .value_hint(clap::ValueHint::FilePath),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("add")
+                .about("variadic last positional")
+                .arg(
+                    clap::Arg::new("paths")
+                        .num_args(1..)
+                        .last(true)
+                        .value_hint(clap::ValueHint::AnyPath),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("exec").about("trailing var arg").arg(
+                clap::Arg::new("cmd")
+                    .num_args(1..)
+                    .trailing_var_arg(true)
+                    .value_hint(clap::ValueHint::CommandName),
+            ),
+        )
+}
+
+pub fn exclusive_flags_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests mutually exclusive flags")
+        .arg(
+            clap::Arg::new("color")
+                .long("color")
+                .global(true)
+                .conflicts_with("no-color")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("no-color")
+                .long("no-color")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("json")
+                .long("json")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(
+            clap::Arg::new("yaml")
+                .long("yaml")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .group(clap::ArgGroup::new("format").args(["json", "yaml"]))
+        .subcommand(
+            clap::Command::new("run")
+                .about("conflicts with global flags")
+                .arg(
+                    clap::Arg::new("list")
+                        .long("list")
+                        .exclusive(true)
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::Arg::new("plain")
+                        .long("plain")
+                        .global(true)
+                        .conflicts_with("mode")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::Arg::new("verbose")
+                        .short('v')
+                        .long("verbose")
+                        .conflicts_with("quiet")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::Arg::new("fast")
+                        .long("fast")
+                        .conflicts_with("slow")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::Arg::new("slow")
+                        .long("slow")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    clap::Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .group(
+                    clap::ArgGroup::new("mode")
+                        .args(["fast", "dry-run"])
+                        .multiple(true),
+                ),
+        )
+}
+
+pub fn custom_actions_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests custom completion macros")
+        .arg(
+            clap::Arg::new("branch")
+                .short('b')
+                .long("branch")
+                .action(clap::ArgAction::Set),
+        )
+        .arg(
+            clap::Arg::new("config")
+                .long("config")
+                .value_hint(clap::ValueHint::FilePath),
+        )
+        .arg(
+            clap::Arg::new("format")
+                .long("format")
+                .value_parser(["json", "yaml"]),
+        )
+        .arg(clap::Arg::new("commit"))
+        .arg(
+            clap::Arg::new("path")
+                .num_args(1..)
+                .last(true)
+                .value_hint(clap::ValueHint::AnyPath),
+        )
+}
+
+pub fn external_subcommands_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests external subcommands")
+        .allow_external_subcommands(true)
+        .subcommand(clap::Command::new("clone").about("builtin subcommand"))
+        .subcommand(
+            clap::Command::new("plugin")
+                .about("external subcommands with possible values")
+                .allow_external_subcommands(true)
+                .external_subcommand_value_parser(clap::builder::PossibleValuesParser::new([
+                    "fast", "slow",
+                ])),
+        )
+}
+
+pub fn parsing_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests parsing modes")
+        .subcommand(
+            clap::Command::new("interspersed")
+                .about("flags anywhere")
+                .arg(
+                    clap::Arg::new("verbose")
+                        .long("verbose")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(clap::Arg::new("files").num_args(1..)),
+        )
+        .subcommand(
+            clap::Command::new("exec")
+                .about("flags before the command")
+                .arg(
+                    clap::Arg::new("env")
+                        .long("env")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    clap::Arg::new("cmd")
+                        .num_args(1..)
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true)
+                        .value_hint(clap::ValueHint::CommandWithArguments),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("wrap")
+                .about("no flags at all")
+                .disable_help_flag(true)
+                .arg(
+                    clap::Arg::new("args")
+                        .num_args(1..)
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true),
+                ),
+        )
+}
+
+pub fn groups_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests command groups")
+        .subcommand_help_heading("Porcelain Commands")
+        .subcommand(clap::Command::new("add").about("add files"))
+        .subcommand(clap::Command::new("commit").about("record changes"))
+        .subcommand(
+            clap::Command::new("remote")
+                .about("manage remotes")
+                .subcommand_help_heading("Remote Commands")
+                .subcommand(clap::Command::new("add").about("add a remote"))
+                .subcommand(clap::Command::new("remove").about("remove a remote")),
+        )
+        .subcommand(
+            clap::Command::new("stash")
+                .about("stash changes")
+                .subcommand(clap::Command::new("push").about("push a stash"))
+                .subcommand(clap::Command::new("pop").about("pop a stash")),
+        )
+}
+
+pub fn help_headings_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests flag help headings")
+        .arg(
+            clap::Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(clap::ArgAction::SetTrue)
+                .help("without heading"),
+        )
+        .arg(
+            clap::Arg::new("color")
+                .long("color")
+                .help_heading("Display")
+                .help("explicit heading"),
+        )
+        .next_help_heading("Network")
+        .arg(
+            clap::Arg::new("proxy")
+                .long("proxy")
+                .help("heading from command"),
+        )
+        .arg(
+            clap::Arg::new("timeout")
+                .long("timeout")
+                .default_value("30")
+                .help("heading and default"),
+        )
+}
+
+pub fn order_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests ordering")
+        .arg(
+            clap::Arg::new("zeta")
+                .long("zeta")
+                .value_parser(["z1", "z2"])
+                .long_help("zeta long help"),
+        )
+        .arg(
+            clap::Arg::new("alpha")
+                .long("alpha")
+                .value_parser(["a1", "a2"])
+                .long_help("alpha long help"),
+        )
+        .arg(
+            clap::Arg::new("mid")
+                .short('m')
+                .display_order(0)
+                .value_parser(["m1", "m2"])
+                .long_help("mid long help"),
+        )
+        .subcommand(clap::Command::new("second"))
+        .subcommand(clap::Command::new("third"))
+        .subcommand(clap::Command::new("first").display_order(0))
+}
+
+pub fn examples_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests examples")
+        .after_help(
+            "Examples:
+  # print a greeting
+  $ examples --loud hello
+  examples hello world
+
+Learn more at https://example.com",
+        )
+        .arg(
+            clap::Arg::new("loud")
+                .long("loud")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .subcommand(
+            clap::Command::new("sub")
+                .about("Subcommand")
+                .after_help("Examples:\n  examples sub")
+                .after_long_help("Examples:\n  # run sub verbosely\n  examples sub --verbose"),
+        )
+}
+
+pub fn shortcuts_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests shortcuts")
+        .subcommand(
+            clap::Command::new("log")
+                .about("Show commits")
+                .arg(
+                    clap::Arg::new("oneline")
+                        .long("oneline")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(clap::Arg::new("revision")),
+        )
+        .subcommand(
+            clap::Command::new("lg")
+                .about("Shortcut for log --oneline")
+                .arg(clap::Arg::new("args").num_args(0..).trailing_var_arg(true)),
+        )
+}
+
+pub fn optional_values_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests optional values")
+        .arg(
+            clap::Arg::new("color")
+                .long("color")
+                .help("When to use colors")
+                .num_args(0..=1)
+                .require_equals(true)
+                .value_parser(["always", "never", "auto"])
+                .default_value("auto")
+                .default_missing_value("always"),
+        )
+        .arg(
+            clap::Arg::new("level")
+                .short('l')
+                .help("Compression level")
+                .long_help("Compression level from 1 to 9")
+                .num_args(0..=1)
+                .default_missing_value("6"),
+        )
+        .arg(
+            clap::Arg::new("name")
+                .long("name")
+                .help("Name to use")
+                .require_equals(true),
+        )
+        .arg(
+            clap::Arg::new("quote")
+                .long("quote")
+                .help("Quote to use")
+                .num_args(0..=1)
+                .default_missing_value("a\"b\nc"),
+        )
+        .arg(
+            clap::Arg::new("tricky")
+                .long("tricky")
+                .help(r#"Looks like default_missing_vals: ["evil"]"#)
+                .num_args(0..=1),
+        )
+}
+
+pub fn positional_slots_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests positional slots")
+        .subcommand(
+            clap::Command::new("add")
+                .arg(clap::Arg::new("name").required(true))
+                .arg(
+                    clap::Arg::new("path")
+                        .required(true)
+                        .value_hint(clap::ValueHint::AnyPath),
+                )
+                .arg(clap::Arg::new("comment")),
+        )
+        .subcommand(
+            clap::Command::new("copy")
+                .arg(clap::Arg::new("name").required(true))
+                .arg(
+                    clap::Arg::new("paths")
+                        .num_args(1..)
+                        .value_hint(clap::ValueHint::DirPath),
+                ),
+        )
+}
+
+pub fn positional_arity_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests positional arity")
+        .subcommand(
+            clap::Command::new("mv-pair").arg(
+                clap::Arg::new("pair")
+                    .required(true)
+                    .num_args(2)
+                    .value_names(["SRC", "DST"])
+                    .value_hint(clap::ValueHint::AnyPath),
+            ),
+        )
+        .subcommand(
+            clap::Command::new("resize")
+                .arg(
+                    clap::Arg::new("size")
+                        .required(true)
+                        .num_args(1..=3)
+                        .value_name("N")
+                        .value_parser(["1", "2", "4"]),
+                )
+                .arg(
+                    clap::Arg::new("target")
+                        .required(true)
+                        .value_hint(clap::ValueHint::DirPath),
+                ),
+        )
+}
+
+pub fn variadic_positionals_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests variadic positionals")
+        .subcommand(
+            clap::Command::new("cp")
+                .arg(
+                    clap::Arg::new("src")
+                        .required(true)
+                        .num_args(1..)
+                        .value_hint(clap::ValueHint::FilePath),
+                )
+                .arg(
+                    clap::Arg::new("dst")
+                        .required(true)
+                        .value_hint(clap::ValueHint::DirPath),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("sync")
+                .arg(
+                    clap::Arg::new("remote")
+                        .required(true)
+                        .value_hint(clap::ValueHint::Hostname),
+                )
+                .arg(
+                    clap::Arg::new("paths")
+                        .required(true)
+                        .num_args(1..)
+                        .value_hint(clap::ValueHint::AnyPath),
+                )
+                .arg(
+                    clap::Arg::new("dst")
+                        .required(true)
+                        .value_hint(clap::ValueHint::AnyPath),
+                ),
+        )
+}
+
+pub fn positional_documentation_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests positional documentation")
+        .subcommand(
+            clap::Command::new("cp")
+                .arg(
+                    clap::Arg::new("pair")
+                        .required(true)
+                        .num_args(2)
+                        .value_names(["SRC", "DST"])
+                        .help("Source and destination"),
+                )
+                .arg(
+                    clap::Arg::new("mode")
+                        .help("File mode")
+                        .long_help("File mode of the copy, in octal"),
+                )
+                .arg(clap::Arg::new("rest").num_args(1..).help("Further files"))
+                .arg(
+                    clap::Arg::new("script")
+                        .last(true)
+                        .help("Script run after copying"),
+                ),
+        )
+        .subcommand(
+            clap::Command::new("exec").arg(
+                clap::Arg::new("command")
+                    .num_args(1..)
+                    .trailing_var_arg(true)
+                    .value_hint(clap::ValueHint::CommandWithArguments)
+                    .help("Command to run"),
+            ),
+        )
+}
+
+pub fn hidden_values_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests hidden possible values")
+        .arg(clap::Arg::new("color").long("color").value_parser([
+            clap::builder::PossibleValue::new("always"),
+            clap::builder::PossibleValue::new("never"),
+            clap::builder::PossibleValue::new("yes").hide(true),
+        ]))
+        .arg(
+            clap::Arg::new("format")
+                .long("format")
+                .value_parser(["json", "yaml"])
+                .hide_possible_values(true),
+        )
+        .arg(
+            clap::Arg::new("input")
+                .value_parser(["-"])
+                .hide_possible_values(true)
+                .value_hint(clap::ValueHint::FilePath),
+        )
+}
+
+pub fn bridge_shift_command(name: &'static str) -> clap::Command {
+    clap::Command::new(name)
+        .about("Tests bridging after positionals")
+        .arg(
+            clap::Arg::new("host")
+                .required(true)
+                .value_hint(clap::ValueHint::Hostname),
+        )
+        .arg(
+            clap::Arg::new("command")
+                .num_args(1..)
+                .trailing_var_arg(true)
+                .value_hint(clap::ValueHint::CommandWithArguments),
+        )
+}
+
 pub(crate) fn assert_matches(
     expected: impl IntoData,
     gen: impl clap_complete::Generator,